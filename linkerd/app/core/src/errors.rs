@@ -6,7 +6,10 @@ use linkerd2_error_metrics as metrics;
 use linkerd2_error_respond as respond;
 pub use linkerd2_error_respond::RespondLayer;
 use linkerd2_proxy_http::HasH2Reason;
-use linkerd2_timeout::{error::ResponseTimeout, FailFastError};
+use linkerd2_timeout::{
+    error::{ItemTimeout, ResponseTimeout},
+    AttemptBudgetExhausted, FailFastError,
+};
 use pin_project::pin_project;
 use std::pin::Pin;
 use std::task::{Context, Poll};
@@ -40,9 +43,11 @@ pub struct HttpError {
 pub enum Reason {
     DispatchTimeout,
     ResponseTimeout,
+    ItemTimeout,
     IdentityRequired,
     Io(Option<Errno>),
     FailFast,
+    AttemptBudgetExhausted,
     GatewayLoop,
     NotFound,
     Unexpected,
@@ -210,10 +215,12 @@ impl<RspB: Default + hyper::body::HttpBody> respond::Respond<http::Response<RspB
 fn http_status(error: &(dyn std::error::Error + 'static)) -> StatusCode {
     if let Some(HttpError { http, .. }) = error.downcast_ref::<HttpError>() {
         *http
-    } else if error.is::<ResponseTimeout>() {
+    } else if error.is::<ResponseTimeout>() || error.is::<ItemTimeout>() {
         http::StatusCode::GATEWAY_TIMEOUT
     } else if error.is::<FailFastError>() {
         http::StatusCode::SERVICE_UNAVAILABLE
+    } else if error.is::<AttemptBudgetExhausted>() {
+        http::StatusCode::SERVICE_UNAVAILABLE
     } else if error.is::<tower::timeout::error::Elapsed>() {
         http::StatusCode::SERVICE_UNAVAILABLE
     } else if error.is::<IdentityRequired>() {
@@ -241,6 +248,14 @@ fn set_grpc_status(
         headers.insert(GRPC_STATUS, code_header(code));
         headers.insert(GRPC_MESSAGE, HeaderValue::from_static("request timed out"));
         code
+    } else if error.is::<ItemTimeout>() {
+        let code = Code::DeadlineExceeded;
+        headers.insert(GRPC_STATUS, code_header(code));
+        headers.insert(
+            GRPC_MESSAGE,
+            HeaderValue::from_static("stream item timed out"),
+        );
+        code
     } else if error.is::<FailFastError>() {
         let code = Code::Unavailable;
         headers.insert(GRPC_STATUS, code_header(code));
@@ -249,6 +264,14 @@ fn set_grpc_status(
             HeaderValue::from_static("proxy max-concurrency exhausted"),
         );
         code
+    } else if error.is::<AttemptBudgetExhausted>() {
+        let code = Code::Unavailable;
+        headers.insert(GRPC_STATUS, code_header(code));
+        headers.insert(
+            GRPC_MESSAGE,
+            HeaderValue::from_static("proxy attempt budget exhausted"),
+        );
+        code
     } else if error.is::<tower::timeout::error::Elapsed>() {
         let code = Code::Unavailable;
         headers.insert(GRPC_STATUS, code_header(code));
@@ -336,8 +359,12 @@ impl LabelError {
             *reason
         } else if err.is::<ResponseTimeout>() {
             Reason::ResponseTimeout
+        } else if err.is::<ItemTimeout>() {
+            Reason::ItemTimeout
         } else if err.is::<FailFastError>() {
             Reason::FailFast
+        } else if err.is::<AttemptBudgetExhausted>() {
+            Reason::AttemptBudgetExhausted
         } else if err.is::<tower::timeout::error::Elapsed>() {
             Reason::DispatchTimeout
         } else if err.is::<IdentityRequired>() {
@@ -367,8 +394,10 @@ impl metrics::FmtLabels for Reason {
             "message=\"{}\"",
             match self {
                 Reason::FailFast => "failfast",
+                Reason::AttemptBudgetExhausted => "attempt budget exhausted",
                 Reason::DispatchTimeout => "dispatch timeout",
                 Reason::ResponseTimeout => "response timeout",
+                Reason::ItemTimeout => "stream item timeout",
                 Reason::IdentityRequired => "identity required",
                 Reason::GatewayLoop => "gateway loop",
                 Reason::NotFound => "not found",
@@ -45,6 +45,28 @@ pub enum Bucket {
 #[derive(Debug)]
 pub struct Bounds(pub &'static [Bucket]);
 
+/// How `Histogram::percentile_with` estimates a value within a bucket.
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+pub enum Interpolation {
+    /// Reports the upper bound of the bucket containing the `p`th
+    /// observation.
+    ///
+    /// This never under-estimates, but it moves in bucket-sized steps.
+    NearestRank,
+
+    /// Interpolates linearly between the lower and upper bounds of the bucket
+    /// containing the `p`th observation, assuming observations are spread
+    /// evenly within it.
+    ///
+    /// This is the default, and is what `Histogram::percentile` uses.
+    Linear,
+}
+
+/// An error indicating that histogram bounds are not strictly increasing or
+/// do not end with `Bucket::Inf`.
+#[derive(Debug)]
+pub struct InvalidBounds(());
+
 /// Helper that lazily formats metric keys as {0}_{1}.
 struct Key<A: fmt::Display, B: fmt::Display>(A, B);
 
@@ -54,21 +76,41 @@ struct Label<K: fmt::Display, V: fmt::Display>(K, V);
 // ===== impl Histogram =====
 
 impl<V: Into<u64>> Histogram<V> {
+    /// Constructs a histogram with the given bucket bounds.
+    ///
+    /// # Panics
+    ///
+    /// If the bounds are not valid (see `try_new`).
     pub fn new(bounds: &'static Bounds) -> Self {
+        Self::try_new(bounds).expect("histogram bounds must be valid")
+    }
+
+    /// Constructs a histogram with the given bucket bounds, which must be
+    /// strictly increasing, greater than zero, and end with `Bucket::Inf`.
+    ///
+    /// The final `Inf` bucket ensures that every value may be recorded, and
+    /// it provides the `+Inf` bucket that Prometheus requires.
+    pub fn try_new(bounds: &'static Bounds) -> Result<Self, InvalidBounds> {
+        if bounds.0.last() != Some(&Bucket::Inf) {
+            return Err(InvalidBounds(()));
+        }
+
         let mut buckets = Vec::with_capacity(bounds.0.len());
         let mut prior = &Bucket::Le(0);
         for bound in bounds.0.iter() {
-            assert!(prior < bound);
+            if prior >= bound {
+                return Err(InvalidBounds(()));
+            }
             buckets.push(Counter::default());
             prior = bound;
         }
 
-        Self {
+        Ok(Self {
             bounds,
             buckets: buckets.into_boxed_slice(),
             sum: Counter::default(),
             _p: PhantomData,
-        }
+        })
     }
 
     pub fn add<U: Into<V>>(&self, u: U) {
@@ -88,6 +130,126 @@ impl<V: Into<u64>> Histogram<V> {
         self.buckets[idx].incr();
         self.sum.add(value);
     }
+
+    /// Adds all of `other`'s observations into this histogram.
+    ///
+    /// # Panics
+    ///
+    /// If `other` does not have the same bucket bounds as this histogram.
+    pub fn merge(&self, other: &Self) {
+        assert_eq!(
+            self.bounds.0, other.bounds.0,
+            "merged histograms must have identical bounds"
+        );
+
+        for (bucket, count) in self.buckets.iter().zip(other.buckets.iter()) {
+            bucket.add(count.value());
+        }
+        self.sum.add(other.sum.value());
+    }
+
+    /// Resets all bucket counts and the sum to zero.
+    ///
+    /// The bucket storage is reused rather than reallocated.
+    pub fn clear(&mut self) {
+        for bucket in self.buckets.iter_mut() {
+            *bucket = Counter::default();
+        }
+        self.sum = Counter::default();
+    }
+
+    /// Returns the upper bound of each bucket, in increasing order.
+    pub fn bounds(&self) -> &'static [Bucket] {
+        self.bounds.0
+    }
+
+    /// Returns an iterator over each bucket's upper bound and count.
+    pub fn buckets(&self) -> impl Iterator<Item = (Bucket, u64)> + '_ {
+        self.into_iter()
+            .map(|(bucket, count)| (*bucket, count.value()))
+    }
+
+    /// Returns the total number of observations in all buckets.
+    pub fn count(&self) -> u64 {
+        self.buckets.iter().map(Counter::value).sum()
+    }
+
+    /// Returns the upper bound of the lowest bucket that holds an observation.
+    pub fn min(&self) -> Option<Bucket> {
+        self.into_iter()
+            .find(|(_, count)| count.value() > 0)
+            .map(|(bucket, _)| *bucket)
+    }
+
+    /// Returns the upper bound of the highest bucket that holds an observation.
+    pub fn max(&self) -> Option<Bucket> {
+        self.into_iter()
+            .rev()
+            .find(|(_, count)| count.value() > 0)
+            .map(|(bucket, _)| *bucket)
+    }
+
+    /// Estimates the value at the `p`th percentile, where `p` is in `[0.0, 1.0]`.
+    ///
+    /// This uses `Interpolation::Linear`; see `percentile_with`.
+    pub fn percentile(&self, p: f64) -> u64 {
+        self.percentile_with(p, Interpolation::Linear)
+    }
+
+    /// Estimates the value at the `p`th percentile, where `p` is in `[0.0, 1.0]`.
+    ///
+    /// The bucket containing the `p`th observation is located and a value
+    /// within it is chosen according to `interpolation`.
+    ///
+    /// The lookup saturates rather than panicking: `p` is clamped to
+    /// `[0.0, 1.0]` (with `NaN` treated as `0.0`), and the result never
+    /// exceeds the upper bound of the highest populated bucket. Because the
+    /// `Inf` bucket has no upper bound, observations that fall into it are
+    /// reported as the largest finite bound. An empty histogram reports `0`.
+    pub fn percentile_with(&self, p: f64, interpolation: Interpolation) -> u64 {
+        // `f64::max` ignores `NaN`.
+        let p = p.max(0.0).min(1.0);
+
+        let count = self.count();
+        if count == 0 {
+            return 0;
+        }
+
+        let rank = p * count as f64;
+        let mut seen = 0;
+        let mut lower = 0;
+        // The upper bound of the highest populated bucket seen so far.
+        let mut populated = 0;
+        for (bucket, counter) in self {
+            let n = counter.value();
+            match *bucket {
+                Bucket::Le(upper) => {
+                    if n > 0 {
+                        if (seen + n) as f64 >= rank {
+                            return match interpolation {
+                                Interpolation::NearestRank => upper,
+                                Interpolation::Linear => {
+                                    let frac = (rank - seen as f64) / n as f64;
+                                    lower + ((upper - lower) as f64 * frac) as u64
+                                }
+                            };
+                        }
+                        populated = upper;
+                    }
+                    lower = upper;
+                }
+                Bucket::Inf => {
+                    if n > 0 {
+                        populated = lower;
+                    }
+                    break;
+                }
+            }
+            seen += n;
+        }
+
+        populated
+    }
 }
 
 #[cfg(any(test, feature = "test_util"))]
@@ -235,6 +397,19 @@ impl<K: fmt::Display, V: fmt::Display> FmtLabels for Label<K, V> {
     }
 }
 
+// ===== impl InvalidBounds =====
+
+impl fmt::Display for InvalidBounds {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "histogram bounds must be strictly increasing and end with +Inf"
+        )
+    }
+}
+
+impl std::error::Error for InvalidBounds {}
+
 // ===== impl Bucket =====
 
 impl fmt::Display for Bucket {
@@ -406,4 +581,274 @@ mod tests {
             true
         }
     }
+
+    #[test]
+    fn percentiles() {
+        let hist = Histogram::<u64>::new(&BOUNDS);
+        for obs in 1..=100u64 {
+            hist.add(obs);
+        }
+
+        assert_eq!(hist.count(), 100);
+        assert_eq!(hist.min(), Some(Bucket::Le(10)));
+        assert_eq!(hist.max(), Some(Bucket::Le(100)));
+        assert_eq!(hist.percentile(0.5), 50);
+        assert_eq!(hist.percentile(0.9), 90);
+        assert_eq!(hist.percentile(0.99), 99);
+    }
+
+    #[test]
+    fn percentile_interpolates_within_bucket() {
+        let hist = Histogram::<u64>::new(&BOUNDS);
+        // Four observations in the (100, 200] bucket.
+        for _ in 0..4 {
+            hist.add(150u64);
+        }
+
+        assert_eq!(hist.min(), Some(Bucket::Le(200)));
+        assert_eq!(hist.max(), Some(Bucket::Le(200)));
+        assert_eq!(hist.percentile(0.0), 100);
+        assert_eq!(hist.percentile(0.25), 125);
+        assert_eq!(hist.percentile(0.5), 150);
+        assert_eq!(hist.percentile(1.0), 200);
+    }
+
+    #[test]
+    fn percentile_interpolation_methods() {
+        let hist = Histogram::<u64>::new(&BOUNDS);
+        for obs in 1..=100u64 {
+            hist.add(obs);
+        }
+
+        assert_eq!(hist.percentile_with(0.55, Interpolation::Linear), 55);
+        assert_eq!(hist.percentile_with(0.55, Interpolation::NearestRank), 60);
+        assert_eq!(hist.percentile_with(0.6, Interpolation::NearestRank), 60);
+
+        // Within a single coarse bucket, nearest-rank always reports the
+        // bucket's upper bound.
+        let hist = Histogram::<u64>::new(&BOUNDS);
+        for _ in 0..4 {
+            hist.add(150u64);
+        }
+        assert_eq!(hist.percentile_with(0.25, Interpolation::Linear), 125);
+        assert_eq!(hist.percentile_with(0.0, Interpolation::NearestRank), 200);
+        assert_eq!(hist.percentile_with(0.25, Interpolation::NearestRank), 200);
+        assert_eq!(hist.percentile(0.25), 125);
+    }
+
+    #[test]
+    fn percentile_of_inf_bucket_is_largest_bound() {
+        let hist = Histogram::<u64>::new(&BOUNDS);
+        hist.add(u64::MAX);
+
+        assert_eq!(hist.max(), Some(Bucket::Inf));
+        assert_eq!(hist.percentile(0.99), 1_000_000);
+        assert_eq!(
+            hist.percentile_with(0.99, Interpolation::NearestRank),
+            1_000_000
+        );
+    }
+
+    #[test]
+    fn clear_resets_counts() {
+        let mut hist = Histogram::<u64>::new(&BOUNDS);
+        for obs in 1..=100u64 {
+            hist.add(obs);
+        }
+
+        hist.clear();
+        assert_eq!(hist.count(), 0);
+        assert_eq!(hist.sum.value(), 0);
+        assert_eq!(hist.min(), None);
+        assert_eq!(hist.percentile(0.5), 0);
+
+        // A cleared histogram behaves like a fresh one.
+        for _ in 0..4 {
+            hist.add(150u64);
+        }
+        assert_eq!(hist.count(), 4);
+        assert_eq!(hist.percentile(0.5), 150);
+    }
+
+    /// Formats a histogram's Prometheus exposition as `latency`.
+    struct Exposition<'a, L>(&'a Histogram<u64>, Option<L>);
+
+    impl<'a, L: FmtLabels> fmt::Display for Exposition<'a, L> {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self.1 {
+                Some(ref labels) => self.0.fmt_metric_labeled(f, "latency", labels),
+                None => self.0.fmt_metric(f, "latency"),
+            }
+        }
+    }
+
+    #[test]
+    fn fmt_exposition() {
+        static SMALL_BOUNDS: &Bounds = &Bounds(&[Bucket::Le(10), Bucket::Inf]);
+
+        let hist = Histogram::<u64>::new(SMALL_BOUNDS);
+        hist.add(5u64);
+        hist.add(1_000u64);
+
+        assert_eq!(
+            Exposition(&hist, None::<Label<&str, &str>>).to_string(),
+            "latency_bucket{le=\"10\"} 1\n\
+             latency_bucket{le=\"+Inf\"} 2\n\
+             latency_count 2\n\
+             latency_sum 1005\n",
+        );
+    }
+
+    #[test]
+    fn fmt_exposition_labeled() {
+        static SMALL_BOUNDS: &Bounds = &Bounds(&[Bucket::Le(10), Bucket::Le(100), Bucket::Inf]);
+
+        let hist = Histogram::<u64>::new(SMALL_BOUNDS);
+        hist.add(5u64);
+        hist.add(50u64);
+        hist.add(50u64);
+
+        assert_eq!(
+            Exposition(&hist, Some(Label("direction", "inbound"))).to_string(),
+            "latency_bucket{direction=\"inbound\",le=\"10\"} 1\n\
+             latency_bucket{direction=\"inbound\",le=\"100\"} 3\n\
+             latency_bucket{direction=\"inbound\",le=\"+Inf\"} 3\n\
+             latency_count{direction=\"inbound\"} 3\n\
+             latency_sum{direction=\"inbound\"} 105\n",
+        );
+    }
+
+    #[test]
+    fn percentile_of_empty_histogram() {
+        let hist = Histogram::<u64>::new(&BOUNDS);
+        assert_eq!(hist.percentile(0.0), 0);
+        assert_eq!(hist.percentile(0.5), 0);
+        assert_eq!(hist.percentile(1.0), 0);
+    }
+
+    #[test]
+    fn percentile_clamps_to_populated_buckets() {
+        let hist = Histogram::<u64>::new(&BOUNDS);
+        hist.add(150u64);
+
+        // A single populated bucket bounds every percentile.
+        assert_eq!(hist.percentile(0.999), 199);
+        assert_eq!(hist.percentile(1.0), 200);
+        assert_eq!(hist.percentile(2.0), 200);
+        assert_eq!(hist.percentile(-1.0), 100);
+        assert_eq!(hist.percentile(std::f64::NAN), 100);
+    }
+
+    #[test]
+    fn percentile_of_sparse_tail() {
+        let hist = Histogram::<u64>::new(&BOUNDS);
+        for _ in 0..99 {
+            hist.add(5u64);
+        }
+        hist.add(900_000u64);
+
+        assert!(hist.percentile(0.5) <= 10);
+        assert!(hist.percentile(0.995) > 800_000);
+        assert_eq!(hist.percentile(1.0), 900_000);
+    }
+
+    #[test]
+    fn buckets_match_bounds() {
+        let hist = Histogram::<u64>::new(&BOUNDS);
+        for obs in 1..=100u64 {
+            hist.add(obs * 7);
+        }
+
+        assert_eq!(hist.bounds(), BOUNDS.0);
+        assert_eq!(hist.buckets().count(), BOUNDS.0.len());
+        assert_eq!(hist.buckets().map(|(_, n)| n).sum::<u64>(), hist.count());
+        assert_eq!(hist.buckets().next(), Some((Bucket::Le(10), 1)));
+    }
+
+    #[test]
+    fn try_new_rejects_invalid_bounds() {
+        static EMPTY: &Bounds = &Bounds(&[]);
+        static ZERO: &Bounds = &Bounds(&[Bucket::Le(0), Bucket::Inf]);
+        static REPEATED: &Bounds = &Bounds(&[Bucket::Le(10), Bucket::Le(10), Bucket::Inf]);
+        static DECREASING: &Bounds = &Bounds(&[Bucket::Le(20), Bucket::Le(10), Bucket::Inf]);
+        static INF_FIRST: &Bounds = &Bounds(&[Bucket::Inf, Bucket::Le(10)]);
+        static NO_INF: &Bounds = &Bounds(&[Bucket::Le(10), Bucket::Le(100)]);
+
+        for bounds in &[EMPTY, ZERO, REPEATED, DECREASING, INF_FIRST, NO_INF] {
+            assert!(Histogram::<u64>::try_new(bounds).is_err(), "{:?}", bounds);
+        }
+    }
+
+    #[test]
+    fn custom_bounds() {
+        // Sub-millisecond buckets, in microseconds.
+        static MICROS: &Bounds = &Bounds(&[
+            Bucket::Le(100),
+            Bucket::Le(250),
+            Bucket::Le(500),
+            Bucket::Le(1_000),
+            Bucket::Inf,
+        ]);
+
+        let hist = Histogram::<u64>::try_new(MICROS).expect("bounds must be valid");
+        for _ in 0..4 {
+            hist.add(300u64);
+        }
+        assert_eq!(hist.percentile(0.5), 375);
+    }
+
+    #[test]
+    fn concurrent_adds() {
+        use std::{sync::Arc, thread};
+
+        const THREADS: u64 = 8;
+        const ADDS: u64 = 10_000;
+
+        let hist = Arc::new(Histogram::<u64>::new(&BOUNDS));
+        let threads = (0..THREADS)
+            .map(|t| {
+                let hist = hist.clone();
+                thread::spawn(move || {
+                    for i in 0..ADDS {
+                        hist.add(t * ADDS + i);
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+        for t in threads {
+            t.join().expect("thread must not panic");
+        }
+
+        assert_eq!(hist.count(), THREADS * ADDS);
+        assert_eq!(hist.sum.value(), (0..THREADS * ADDS).sum::<u64>());
+    }
+
+    #[test]
+    fn merge_sums_buckets() {
+        let a = Histogram::<u64>::new(&BOUNDS);
+        let b = Histogram::<u64>::new(&BOUNDS);
+        for obs in 1..=50u64 {
+            a.add(obs);
+        }
+        for obs in 51..=100u64 {
+            b.add(obs);
+        }
+
+        a.merge(&b);
+        assert_eq!(a.count(), 100);
+        assert_eq!(a.sum.value(), (1..=100).sum::<u64>());
+        assert_eq!(a.percentile(0.9), 90);
+        // The merged-in histogram is unchanged.
+        assert_eq!(b.count(), 50);
+    }
+
+    #[test]
+    #[should_panic]
+    fn merge_requires_identical_bounds() {
+        static OTHER_BOUNDS: &Bounds = &Bounds(&[Bucket::Le(10), Bucket::Inf]);
+
+        let a = Histogram::<u64>::new(&BOUNDS);
+        let b = Histogram::<u64>::new(OTHER_BOUNDS);
+        a.merge(&b);
+    }
 }
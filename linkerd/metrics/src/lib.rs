@@ -12,7 +12,7 @@ mod serve;
 
 pub use self::counter::Counter;
 pub use self::gauge::Gauge;
-pub use self::histogram::Histogram;
+pub use self::histogram::{Bounds, Bucket, Histogram, Interpolation, InvalidBounds};
 pub use self::prom::{FmtLabels, FmtMetric, FmtMetrics, Metric};
 pub use self::scopes::Scopes;
 pub use self::serve::Serve;
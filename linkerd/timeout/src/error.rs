@@ -5,10 +5,35 @@ use std::time::Duration;
 
 /// An error representing that an operation timed out.
 #[derive(Debug)]
-pub struct ResponseTimeout(pub(crate) Duration);
+pub struct ResponseTimeout(pub(crate) Duration, pub(crate) Severity);
 
-/// A duration which pretty-prints as fractional seconds.
-#[derive(Copy, Clone, Debug)]
+/// An error representing that a stream did not produce an item in time.
+#[derive(Debug)]
+pub struct ItemTimeout(pub(crate) Duration);
+
+/// Indicates how noteworthy a timeout is, so that it may be logged at an
+/// appropriate level.
+///
+/// A `Timeout` reports `Warn` when it has fired frequently in the recent
+/// past; see `Timeout::with_severity_window`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warn,
+}
+
+/// An error representing that a `Timeout` was constructed with a duration
+/// that is zero or exceeds `MAX_TIMEOUT`.
+#[derive(Debug)]
+pub struct InvalidTimeout(pub(crate) Duration);
+
+/// A duration which pretty-prints as fractional seconds, or in the largest of
+/// milliseconds, microseconds, or nanoseconds that it fills if it's less than
+/// a second.
+///
+/// A formatting precision (e.g. `{:.1}`) limits the number of fractional
+/// digits. Comparisons delegate to the inner `Duration`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 pub(crate) struct HumanDuration<'a>(pub &'a Duration);
 
 // === impl ResponseTimeout ===
@@ -18,6 +43,11 @@ impl ResponseTimeout {
     pub fn duration(&self) -> Duration {
         self.0
     }
+
+    /// Get how noteworthy this timeout is.
+    pub fn severity(&self) -> Severity {
+        self.1
+    }
 }
 
 impl fmt::Display for ResponseTimeout {
@@ -28,16 +58,125 @@ impl fmt::Display for ResponseTimeout {
 
 impl std::error::Error for ResponseTimeout {}
 
+// === impl ItemTimeout ===
+
+impl ItemTimeout {
+    /// Get the amount of time waited for an item until this error was
+    /// triggered.
+    pub fn duration(&self) -> Duration {
+        self.0
+    }
+}
+
+impl fmt::Display for ItemTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "stream item timed out after {}", HumanDuration(&self.0))
+    }
+}
+
+impl std::error::Error for ItemTimeout {}
+
+// === impl InvalidTimeout ===
+
+impl InvalidTimeout {
+    /// Get the duration that was rejected.
+    pub fn duration(&self) -> Duration {
+        self.0
+    }
+}
+
+impl fmt::Display for InvalidTimeout {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.0 == Duration::from_secs(0) {
+            return write!(
+                f,
+                "timeout must not be zero; use a passthru timeout instead"
+            );
+        }
+        write!(
+            f,
+            "timeout of {} exceeds the maximum of {}",
+            HumanDuration(&self.0),
+            HumanDuration(&crate::MAX_TIMEOUT)
+        )
+    }
+}
+
+impl std::error::Error for InvalidTimeout {}
+
 // === HumanDuration ===
 
+impl<'a> HumanDuration<'a> {
+    pub fn as_duration(&self) -> Duration {
+        *self.0
+    }
+}
+
+impl<'a> From<&'a Duration> for HumanDuration<'a> {
+    fn from(duration: &'a Duration) -> Self {
+        HumanDuration(duration)
+    }
+}
+
 impl<'a> fmt::Display for HumanDuration<'a> {
     fn fmt(&self, fmt: &mut fmt::Formatter<'_>) -> fmt::Result {
-        let secs = self.0.as_secs();
-        let subsec_ms = self.0.subsec_nanos() as f64 / 1_000_000f64;
-        if secs == 0 {
-            write!(fmt, "{}ms", subsec_ms)
+        let duration = self.as_duration();
+        let nanos = duration.subsec_nanos();
+        let (value, unit) = if duration.as_secs() > 0 {
+            (duration.as_nanos() as f64 / 1_000_000_000f64, "s")
+        } else if nanos >= 1_000_000 {
+            (nanos as f64 / 1_000_000f64, "ms")
+        } else if nanos >= 1_000 {
+            (nanos as f64 / 1_000f64, "µs")
         } else {
-            write!(fmt, "{}s", secs as f64 + subsec_ms)
+            (nanos as f64, "ns")
+        };
+
+        match fmt.precision() {
+            Some(precision) => write!(fmt, "{:.*}{}", precision, value, unit),
+            None => write!(fmt, "{}{}", value, unit),
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::HumanDuration;
+    use std::time::Duration;
+
+    #[test]
+    fn fmt_human_duration() {
+        let ms = Duration::from_micros(500_123);
+        assert_eq!(format!("{}", HumanDuration(&ms)), "500.123ms");
+        assert_eq!(format!("{:.0}", HumanDuration(&ms)), "500ms");
+        assert_eq!(format!("{:.1}", HumanDuration(&ms)), "500.1ms");
+
+        let secs = Duration::from_millis(1_600);
+        assert_eq!(format!("{}", HumanDuration(&secs)), "1.6s");
+        assert_eq!(format!("{:.0}", HumanDuration(&secs)), "2s");
+        assert_eq!(format!("{:.2}", HumanDuration(&secs)), "1.60s");
+    }
+
+    #[test]
+    fn fmt_sub_millisecond_durations() {
+        let fmt = |nanos| HumanDuration(&Duration::from_nanos(nanos)).to_string();
+        assert_eq!(fmt(500), "500ns");
+        assert_eq!(fmt(1_000), "1µs");
+        assert_eq!(fmt(1_500), "1.5µs");
+        assert_eq!(fmt(999_000), "999µs");
+        assert_eq!(fmt(1_000_000), "1ms");
+    }
+
+    #[test]
+    fn compare_human_durations() {
+        let short = Duration::from_millis(500);
+        let long = Duration::from_secs(2);
+        assert_eq!(HumanDuration::from(&short), HumanDuration(&short));
+        assert_eq!(HumanDuration::from(&short).as_duration(), short);
+        assert_ne!(HumanDuration(&short), HumanDuration(&long));
+
+        let mut durations = vec![HumanDuration(&long), HumanDuration(&short)];
+        durations.sort();
+        assert_eq!(durations, vec![HumanDuration(&short), HumanDuration(&long)]);
+    }
+}
@@ -0,0 +1,221 @@
+//! Middleware that recovers from timeouts.
+
+use crate::error::ResponseTimeout;
+use futures::TryFuture;
+use linkerd2_error::Error;
+use pin_project::pin_project;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use tower::util::{Oneshot, ServiceExt};
+use tracing::debug;
+
+/// Responds with a fallback value when the inner service fails with a
+/// `ResponseTimeout`.
+///
+/// Other errors are returned unchanged.
+#[derive(Clone, Debug)]
+pub struct Fallback<S, F> {
+    inner: S,
+    fallback: F,
+}
+
+#[pin_project]
+pub struct FallbackFuture<F, R> {
+    #[pin]
+    inner: F,
+    fallback: R,
+}
+
+/// Calls a fallback service with the original request when the inner service
+/// fails with a `ResponseTimeout`.
+///
+/// Other errors are returned unchanged.
+#[derive(Clone, Debug)]
+pub struct OrElse<S, F> {
+    inner: S,
+    fallback: F,
+}
+
+#[pin_project(project = OrElseFutureProj)]
+pub enum OrElseFuture<F, S: tower::Service<Req>, Req> {
+    Primary {
+        #[pin]
+        future: F,
+        fallback: Option<(S, Req)>,
+    },
+    Fallback(#[pin] Oneshot<S, Req>),
+}
+
+// === impl Fallback ===
+
+impl<S, F> Fallback<S, F> {
+    pub fn new(inner: S, fallback: F) -> Self {
+        Self { inner, fallback }
+    }
+}
+
+impl<S, F, Req> tower::Service<Req> for Fallback<S, F>
+where
+    S: tower::Service<Req>,
+    S::Error: Into<Error>,
+    F: Fn() -> S::Response + Clone,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = FallbackFuture<S::Future, F>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        FallbackFuture {
+            inner: self.inner.call(req),
+            fallback: self.fallback.clone(),
+        }
+    }
+}
+
+// === impl FallbackFuture ===
+
+impl<F, R> Future for FallbackFuture<F, R>
+where
+    F: TryFuture,
+    F::Error: Into<Error>,
+    R: Fn() -> F::Ok,
+{
+    type Output = Result<F::Ok, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.project();
+        match futures::ready!(this.inner.try_poll(cx)) {
+            Ok(rsp) => Poll::Ready(Ok(rsp)),
+            Err(e) => {
+                let error: Error = e.into();
+                if error.is::<ResponseTimeout>() {
+                    debug!(%error, "Using fallback response");
+                    return Poll::Ready(Ok((*this.fallback)()));
+                }
+                Poll::Ready(Err(error))
+            }
+        }
+    }
+}
+
+// === impl OrElse ===
+
+impl<S, F> OrElse<S, F> {
+    pub fn new(inner: S, fallback: F) -> Self {
+        Self { inner, fallback }
+    }
+}
+
+impl<S, F, Req> tower::Service<Req> for OrElse<S, F>
+where
+    Req: Clone,
+    S: tower::Service<Req>,
+    S::Error: Into<Error>,
+    F: tower::Service<Req, Response = S::Response> + Clone,
+    F::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = OrElseFuture<S::Future, F, Req>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        // The fallback is only driven to readiness if it's needed.
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, req: Req) -> Self::Future {
+        OrElseFuture::Primary {
+            fallback: Some((self.fallback.clone(), req.clone())),
+            future: self.inner.call(req),
+        }
+    }
+}
+
+// === impl OrElseFuture ===
+
+impl<F, S, Req> Future for OrElseFuture<F, S, Req>
+where
+    F: TryFuture<Ok = S::Response>,
+    F::Error: Into<Error>,
+    S: tower::Service<Req>,
+    S::Error: Into<Error>,
+{
+    type Output = Result<S::Response, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        loop {
+            match self.as_mut().project() {
+                OrElseFutureProj::Primary { future, fallback } => {
+                    let error: Error = match futures::ready!(future.try_poll(cx)) {
+                        Ok(rsp) => return Poll::Ready(Ok(rsp)),
+                        Err(e) => e.into(),
+                    };
+                    if !error.is::<ResponseTimeout>() {
+                        return Poll::Ready(Err(error));
+                    }
+
+                    debug!(%error, "Calling fallback service");
+                    let (svc, req) = fallback.take().expect("polled after complete");
+                    self.set(OrElseFuture::Fallback(svc.oneshot(req)));
+                }
+                OrElseFutureProj::Fallback(future) => {
+                    return future.poll(cx).map_err(Into::into);
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::Timeout;
+    use futures::future;
+    use linkerd2_error::Error;
+    use std::time::Duration;
+    use tokio::time;
+    use tokio_test::{assert_pending, assert_ready_ok, task};
+    use tower::Service;
+
+    #[tokio::test]
+    async fn falls_back_on_timeout() {
+        time::pause();
+        let inner = tower::service_fn(|()| future::pending::<Result<&'static str, Error>>());
+        let mut svc = Timeout::new(inner, Duration::from_secs(1)).with_fallback(|| "fallback");
+
+        let mut fut = task::spawn(svc.call(()));
+        assert_pending!(fut.poll());
+
+        time::advance(Duration::from_millis(1_001)).await;
+        assert_eq!(assert_ready_ok!(fut.poll()), "fallback");
+    }
+
+    #[tokio::test]
+    async fn propagates_inner_errors() {
+        let inner = tower::service_fn(|()| future::err::<&'static str, Error>("boom".into()));
+        let mut svc = Timeout::new(inner, Duration::from_secs(1)).with_fallback(|| "fallback");
+
+        let err = svc.call(()).await.expect_err("must fail");
+        assert_eq!(err.to_string(), "boom");
+    }
+
+    #[tokio::test]
+    async fn calls_fallback_service_on_timeout() {
+        time::pause();
+        let inner = tower::service_fn(|_: &'static str| future::pending::<Result<String, Error>>());
+        let fallback = tower::service_fn(|req: &'static str| {
+            future::ok::<_, Error>(format!("cached {}", req))
+        });
+        let mut svc = Timeout::new(inner, Duration::from_secs(1)).or_else(fallback);
+
+        let mut fut = task::spawn(svc.call("req"));
+        assert_pending!(fut.poll());
+
+        time::advance(Duration::from_millis(1_001)).await;
+        assert_eq!(assert_ready_ok!(fut.poll()), "cached req");
+    }
+}
@@ -8,44 +8,256 @@ use linkerd2_stack::Proxy;
 use pin_project::pin_project;
 use std::future::Future;
 use std::pin::Pin;
+use std::sync::Arc;
 use std::task::{Context, Poll};
 use std::time::Duration;
-use tokio::time;
+use tokio::time::{self, Instant};
 use tokio_connect::Connect;
 
 pub mod error;
 mod failfast;
+mod fallback;
+mod retry;
+mod severity;
+mod stream;
 
 pub use self::failfast::{FailFast, FailFastError, FailFastLayer};
+pub use self::fallback::{Fallback, OrElse};
+pub use self::retry::{AttemptBudget, AttemptBudgetExhausted, RetryOnTimeout, RetryOnTimeoutLayer};
+use self::severity::SeverityWindow;
+pub use self::stream::TimeoutStream;
+
+/// The longest duration accepted by `Timeout::try_new`.
+pub const MAX_TIMEOUT: Duration = Duration::from_secs(60 * 60 * 24 * 365);
 
 /// A timeout that wraps an underlying operation.
-#[derive(Debug, Clone)]
+///
+/// Errors from the inner operation are passed through unwrapped. When
+/// `Timeout`s are stacked, this means a call fails with a single
+/// `ResponseTimeout` for whichever deadline fired first.
+///
+/// Deadlines are tracked by tokio's timer, so tests can drive a `Timeout`
+/// deterministically by pausing and advancing tokio's clock (see
+/// `tokio::time::pause`) rather than sleeping.
 pub struct Timeout<T> {
     inner: T,
     duration: Option<Duration>,
+    deadline: Option<Instant>,
+    arm_on_ready: bool,
+    armed_at: Option<Instant>,
+    ready: bool,
+    on_complete: Option<OnComplete>,
+    severity: Option<SeverityWindow>,
 }
 
+/// Called with the consumed and remaining budget when a call succeeds.
+type OnComplete = Arc<dyn Fn(Duration, Duration) + Send + Sync>;
+
 #[pin_project(project = TimeoutFutureProj)]
 pub enum TimeoutFuture<F> {
     Passthru(#[pin] F),
-    Timeout(#[pin] time::Timeout<F>, Duration),
+    Timeout {
+        #[pin]
+        future: time::Timeout<F>,
+        duration: Duration,
+        start: Instant,
+        deadline: Instant,
+        on_complete: Option<OnComplete>,
+        severity: Option<SeverityWindow>,
+    },
 }
 
 //===== impl Timeout =====
 
 impl<T> Timeout<T> {
     /// Construct a new `Timeout` wrapping `inner`.
+    ///
+    /// The duration is not validated. Deadlines are capped at `MAX_TIMEOUT`
+    /// so that an absurdly large duration can't overflow, which means it
+    /// effectively never times out. Use `try_new` to reject such durations.
     pub fn new(inner: T, duration: Duration) -> Self {
         Timeout {
             inner,
             duration: Some(duration),
+            deadline: None,
+            arm_on_ready: false,
+            armed_at: None,
+            ready: false,
+            on_complete: None,
+            severity: None,
+        }
+    }
+
+    /// Construct a new `Timeout` wrapping `inner`, failing if `duration` is
+    /// zero or exceeds `MAX_TIMEOUT`.
+    ///
+    /// A zero timeout would fail every call; use `passthru` to disable the
+    /// timeout instead.
+    pub fn try_new(inner: T, duration: Duration) -> Result<Self, error::InvalidTimeout> {
+        if duration == Duration::from_secs(0) || duration > MAX_TIMEOUT {
+            return Err(error::InvalidTimeout(duration));
         }
+        Ok(Self::new(inner, duration))
     }
 
+    /// Construct a `Timeout` that never times out.
+    ///
+    /// Calls are forwarded to `inner` without arming a timer.
     pub fn passthru(inner: T) -> Self {
         Timeout {
             inner,
             duration: None,
+            deadline: None,
+            arm_on_ready: false,
+            armed_at: None,
+            ready: false,
+            on_complete: None,
+            severity: None,
+        }
+    }
+
+    /// Construct a `Timeout` that fails calls made after `deadline`.
+    ///
+    /// Every call shares the same fixed deadline, though a call never waits
+    /// longer than `MAX_TIMEOUT`. Timeouts report the budget that remained
+    /// when the `Timeout` was constructed.
+    pub fn until(inner: T, deadline: Instant) -> Self {
+        Timeout {
+            inner,
+            duration: Some(deadline.saturating_duration_since(Instant::now())),
+            deadline: Some(deadline),
+            arm_on_ready: false,
+            armed_at: None,
+            ready: false,
+            on_complete: None,
+            severity: None,
+        }
+    }
+
+    /// Arms the deadline when the service is first polled for readiness,
+    /// rather than when it is called.
+    ///
+    /// This way, time spent waiting for the inner service to become ready,
+    /// and any delay before the caller issues the request, counts against the
+    /// timeout.
+    ///
+    /// If a service that is already ready is polled for readiness again (e.g.
+    /// by a balancer's ready cache), the deadline is re-armed, so time that a
+    /// ready service spends unused isn't charged to its next call. A caller
+    /// that holds a ready service without re-polling it before calling,
+    /// however, carries the original start time into the call, which may
+    /// then time out immediately.
+    pub fn arm_on_ready(self) -> Self {
+        Self {
+            arm_on_ready: true,
+            ..self
+        }
+    }
+
+    /// Invokes `on_complete` whenever a call succeeds before its deadline,
+    /// with the time the call consumed and the time that remained.
+    ///
+    /// This informs latency-budget accounting, e.g. to set the deadlines of
+    /// downstream calls.
+    pub fn on_complete<F>(self, on_complete: F) -> Self
+    where
+        F: Fn(Duration, Duration) + Send + Sync + 'static,
+    {
+        Self {
+            on_complete: Some(Arc::new(on_complete)),
+            ..self
+        }
+    }
+
+    /// Reports timeouts as `Severity::Warn` once more than `threshold` of
+    /// them have fired within `window`.
+    ///
+    /// Otherwise, timeouts are reported as `Severity::Info`. Clones of this
+    /// `Timeout` share the same window.
+    pub fn with_severity_window(self, window: Duration, threshold: usize) -> Self {
+        Self {
+            severity: Some(SeverityWindow::new(window, threshold)),
+            ..self
+        }
+    }
+
+    /// Wraps this `Timeout` so that, when it elapses, calls resolve to
+    /// `fallback()` rather than failing.
+    pub fn with_fallback<F>(self, fallback: F) -> Fallback<Self, F> {
+        Fallback::new(self, fallback)
+    }
+
+    /// Wraps this `Timeout` so that, when it elapses, the request is retried
+    /// on the `fallback` service.
+    pub fn or_else<F>(self, fallback: F) -> OrElse<Self, F> {
+        OrElse::new(self, fallback)
+    }
+
+    /// Returns a reference to the wrapped inner value.
+    pub fn get_ref(&self) -> &T {
+        &self.inner
+    }
+
+    /// Returns a mutable reference to the wrapped inner value.
+    pub fn get_mut(&mut self) -> &mut T {
+        &mut self.inner
+    }
+
+    /// Consumes the `Timeout`, returning the wrapped inner value.
+    pub fn into_inner(self) -> T {
+        self.inner
+    }
+
+    fn future<F>(&self, inner: F, start: Instant) -> TimeoutFuture<F> {
+        let duration = match self.duration {
+            Some(duration) => duration,
+            None => return TimeoutFuture::Passthru(inner),
+        };
+        // Capping the deadline keeps `Instant` arithmetic from overflowing and
+        // keeps far-future deadlines within the range of tokio's timer.
+        let deadline = match self.deadline {
+            Some(deadline) => deadline.min(start + MAX_TIMEOUT),
+            None => start + duration.min(MAX_TIMEOUT),
+        };
+        TimeoutFuture::Timeout {
+            future: time::timeout_at(deadline, inner),
+            duration,
+            start,
+            deadline,
+            on_complete: self.on_complete.clone(),
+            severity: self.severity.clone(),
+        }
+    }
+}
+
+impl<T: std::fmt::Debug> std::fmt::Debug for Timeout<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Timeout")
+            .field("inner", &self.inner)
+            .field("duration", &self.duration)
+            .field("deadline", &self.deadline)
+            .field("arm_on_ready", &self.arm_on_ready)
+            .field("armed_at", &self.armed_at)
+            .field("ready", &self.ready)
+            .field("on_complete", &self.on_complete.is_some())
+            .field("severity", &self.severity)
+            .finish()
+    }
+}
+
+impl<T: Clone> Clone for Timeout<T> {
+    fn clone(&self) -> Self {
+        // A clone has not been polled for readiness, so it must arm its own
+        // deadline.
+        Self {
+            inner: self.inner.clone(),
+            duration: self.duration,
+            deadline: self.deadline,
+            arm_on_ready: self.arm_on_ready,
+            armed_at: None,
+            ready: false,
+            on_complete: self.on_complete.clone(),
+            severity: self.severity.clone(),
         }
     }
 }
@@ -61,11 +273,7 @@ where
     type Future = TimeoutFuture<P::Future>;
 
     fn proxy(&self, svc: &mut S, req: Req) -> Self::Future {
-        let inner = self.inner.proxy(svc, req);
-        match self.duration {
-            None => TimeoutFuture::Passthru(inner),
-            Some(t) => TimeoutFuture::Timeout(time::timeout(t, inner), t),
-        }
+        self.future(self.inner.proxy(svc, req), Instant::now())
     }
 }
 
@@ -79,15 +287,25 @@ where
     type Future = TimeoutFuture<S::Future>;
 
     fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
-        self.inner.poll_ready(cx).map_err(Into::into)
+        if !self.arm_on_ready {
+            return self.inner.poll_ready(cx).map_err(Into::into);
+        }
+
+        // If the service was already ready, it's being checked again after
+        // sitting unused, so that time isn't charged to the next call.
+        if self.armed_at.is_none() || self.ready {
+            self.armed_at = Some(Instant::now());
+        }
+        let poll = self.inner.poll_ready(cx);
+        self.ready = matches!(poll, Poll::Ready(Ok(())));
+        poll.map_err(Into::into)
     }
 
     fn call(&mut self, req: Req) -> Self::Future {
+        self.ready = false;
+        let start = self.armed_at.take().unwrap_or_else(Instant::now);
         let inner = self.inner.call(req);
-        match self.duration {
-            None => TimeoutFuture::Passthru(inner),
-            Some(t) => TimeoutFuture::Timeout(time::timeout(t, inner), t),
-        }
+        self.future(inner, start)
     }
 }
 
@@ -101,12 +319,23 @@ where
     type Future = Compat<TimeoutFuture<Compat01As03<C::Future>>>;
 
     fn connect(&self) -> Self::Future {
-        let inner = self.inner.connect();
-        match self.duration {
-            None => TimeoutFuture::Passthru(inner.compat()),
-            Some(t) => TimeoutFuture::Timeout(time::timeout(t, inner.compat()), t),
+        self.future(self.inner.connect().compat(), Instant::now())
+            .compat()
+    }
+}
+
+//===== impl TimeoutFuture =====
+
+impl<F> TimeoutFuture<F> {
+    /// Returns the time left before this future times out, or `None` if it
+    /// has no timeout.
+    pub fn remaining(&self) -> Option<Duration> {
+        match self {
+            TimeoutFuture::Passthru(_) => None,
+            TimeoutFuture::Timeout { deadline, .. } => {
+                Some(deadline.saturating_duration_since(Instant::now()))
+            }
         }
-        .compat()
     }
 }
 
@@ -119,11 +348,30 @@ where
     fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
         match self.project() {
             TimeoutFutureProj::Passthru(f) => f.poll(cx).map_err(Into::into),
-            TimeoutFutureProj::Timeout(f, duration) => {
+            TimeoutFutureProj::Timeout {
+                future,
+                duration,
+                start,
+                deadline,
+                on_complete,
+                severity,
+            } => {
                 // If the `timeout` future failed, the error is aways "elapsed";
                 // errors from the underlying future will be in the success arm.
-                let ready = futures::ready!(f.poll(cx))
-                    .map_err(|_| error::ResponseTimeout(*duration).into());
+                let ready = futures::ready!(future.poll(cx)).map_err(|_| {
+                    let severity = match severity {
+                        Some(window) => window.record(),
+                        None => error::Severity::Info,
+                    };
+                    error::ResponseTimeout(*duration, severity).into()
+                });
+                if let (Some(on_complete), Ok(Ok(_))) = (on_complete.as_ref(), &ready) {
+                    let now = Instant::now();
+                    on_complete(
+                        now.saturating_duration_since(*start),
+                        deadline.saturating_duration_since(now),
+                    );
+                }
                 // If the inner future failed but the timeout was not elapsed,
                 // then `ready` will be an `Ok(Err(e))`, so we need to convert
                 // the inner error as well.
@@ -133,3 +381,315 @@ where
         }
     }
 }
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use futures::future;
+    use tower::Service;
+
+    #[tokio::test]
+    async fn remaining_shrinks() {
+        time::pause();
+        let inner = tower::service_fn(|()| future::pending::<Result<(), Error>>());
+        let mut svc = Timeout::new(inner, Duration::from_secs(10));
+        let fut = svc.call(());
+        assert_eq!(fut.remaining(), Some(Duration::from_secs(10)));
+
+        time::advance(Duration::from_secs(3)).await;
+        assert_eq!(fut.remaining(), Some(Duration::from_secs(7)));
+
+        time::advance(Duration::from_secs(10)).await;
+        assert_eq!(fut.remaining(), Some(Duration::from_secs(0)));
+    }
+
+    #[tokio::test]
+    async fn fires_at_deadline_on_paused_clock() {
+        time::pause();
+        let inner = tower::service_fn(|()| future::pending::<Result<(), Error>>());
+        let mut svc = Timeout::new(inner, Duration::from_secs(1));
+        let mut fut = tokio_test::task::spawn(svc.call(()));
+
+        time::advance(Duration::from_millis(999)).await;
+        tokio_test::assert_pending!(fut.poll());
+
+        time::advance(Duration::from_millis(1)).await;
+        let err = tokio_test::assert_ready_err!(fut.poll());
+        assert!(err.is::<error::ResponseTimeout>());
+    }
+
+    #[tokio::test]
+    async fn huge_durations_do_not_overflow() {
+        time::pause();
+        let inner = tower::service_fn(|()| future::pending::<Result<(), Error>>());
+        let mut svc = Timeout::new(inner, Duration::from_secs(std::u64::MAX));
+        let fut = svc.call(());
+        assert_eq!(fut.remaining(), Some(MAX_TIMEOUT));
+
+        let mut fut = tokio_test::task::spawn(fut);
+
+        // Absolute deadlines are capped as well.
+        let inner = tower::service_fn(|()| future::pending::<Result<(), Error>>());
+        let far = Instant::now() + Duration::from_secs(10 * 365 * 24 * 3600);
+        let mut svc = Timeout::until(inner, far);
+        let until = svc.call(());
+        assert_eq!(until.remaining(), Some(MAX_TIMEOUT));
+        let mut until = tokio_test::task::spawn(until);
+
+        time::advance(Duration::from_secs(60 * 60 * 24)).await;
+        tokio_test::assert_pending!(fut.poll());
+        tokio_test::assert_pending!(until.poll());
+    }
+
+    #[tokio::test]
+    async fn nested_timeouts_share_outer_deadline() {
+        time::pause();
+        let inner = tower::service_fn(|()| future::pending::<Result<(), Error>>());
+        let inner = Timeout::new(inner, Duration::from_secs(10));
+        let mut svc = Timeout::new(inner, Duration::from_secs(1));
+        let mut fut = tokio_test::task::spawn(svc.call(()));
+        tokio_test::assert_pending!(fut.poll());
+
+        // The outer deadline fires first and drops the inner timeout with it.
+        time::advance(Duration::from_millis(1_001)).await;
+        let err = tokio_test::assert_ready_err!(fut.poll());
+        let timeout = err
+            .downcast_ref::<error::ResponseTimeout>()
+            .expect("must time out");
+        assert_eq!(timeout.duration(), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn nested_timeouts_do_not_wrap_inner_timeout() {
+        time::pause();
+        let inner = tower::service_fn(|()| future::pending::<Result<(), Error>>());
+        let inner = Timeout::new(inner, Duration::from_secs(1));
+        let mut svc = Timeout::new(inner, Duration::from_secs(10));
+        let mut fut = tokio_test::task::spawn(svc.call(()));
+        tokio_test::assert_pending!(fut.poll());
+
+        // The inner timeout's error is returned as-is by the outer timeout.
+        time::advance(Duration::from_millis(1_001)).await;
+        let err = tokio_test::assert_ready_err!(fut.poll());
+        let timeout = err
+            .downcast_ref::<error::ResponseTimeout>()
+            .expect("must time out");
+        assert_eq!(timeout.duration(), Duration::from_secs(1));
+    }
+
+    #[tokio::test]
+    async fn boxes_non_error_inner_errors() {
+        let inner = tower::service_fn(|()| future::err::<(), &'static str>("boom"));
+        let mut svc = Timeout::new(inner, Duration::from_secs(1));
+
+        let err: Error = svc.call(()).await.expect_err("must fail");
+        assert_eq!(err.to_string(), "boom");
+        assert!(!err.is::<error::ResponseTimeout>());
+    }
+
+    #[tokio::test]
+    async fn arms_deadline_on_ready() {
+        time::pause();
+        let (inner, mut handle) = tower_test::mock::pair::<(), ()>();
+        let svc = Timeout::new(inner, Duration::from_secs(1)).arm_on_ready();
+        let mut svc = tower_test::mock::Spawn::new(svc);
+
+        handle.allow(0);
+        tokio_test::assert_pending!(svc.poll_ready());
+        time::advance(Duration::from_millis(600)).await;
+
+        handle.allow(1);
+        tokio_test::assert_ready_ok!(svc.poll_ready());
+        let fut = svc.call(());
+        // The time spent waiting for readiness is not available to the call.
+        assert_eq!(fut.remaining(), Some(Duration::from_millis(400)));
+
+        let mut fut = tokio_test::task::spawn(fut);
+        tokio_test::assert_pending!(fut.poll());
+        time::advance(Duration::from_millis(401)).await;
+        let err = tokio_test::assert_ready_err!(fut.poll());
+        assert!(err.is::<error::ResponseTimeout>());
+    }
+
+    #[tokio::test]
+    async fn rearms_when_ready_service_is_repolled() {
+        time::pause();
+        let (inner, mut handle) = tower_test::mock::pair::<(), ()>();
+        let svc = Timeout::new(inner, Duration::from_secs(1)).arm_on_ready();
+        let mut svc = tower_test::mock::Spawn::new(svc);
+
+        handle.allow(2);
+        tokio_test::assert_ready_ok!(svc.poll_ready());
+
+        // The service sits ready and unused, e.g. in a ready cache, and is
+        // checked again before it's called.
+        time::advance(Duration::from_secs(10)).await;
+        tokio_test::assert_ready_ok!(svc.poll_ready());
+        let fut = svc.call(());
+        assert_eq!(fut.remaining(), Some(Duration::from_secs(1)));
+    }
+
+    #[tokio::test]
+    async fn until_past_deadline_times_out_immediately() {
+        time::pause();
+        let deadline = Instant::now();
+        time::advance(Duration::from_secs(1)).await;
+
+        let inner = tower::service_fn(|()| future::pending::<Result<(), Error>>());
+        let mut svc = Timeout::until(inner, deadline);
+        let mut fut = tokio_test::task::spawn(svc.call(()));
+        let err = tokio_test::assert_ready_err!(fut.poll());
+        assert!(err.is::<error::ResponseTimeout>());
+    }
+
+    #[tokio::test]
+    async fn until_reports_original_budget() {
+        time::pause();
+        let inner = tower::service_fn(|()| future::pending::<Result<(), Error>>());
+        let mut svc = Timeout::until(inner, Instant::now() + Duration::from_secs(2));
+
+        // Calls made later share the same deadline.
+        time::advance(Duration::from_secs(1)).await;
+        let mut fut = tokio_test::task::spawn(svc.call(()));
+        tokio_test::assert_pending!(fut.poll());
+
+        time::advance(Duration::from_millis(1_001)).await;
+        let err = tokio_test::assert_ready_err!(fut.poll());
+        let timeout = err
+            .downcast_ref::<error::ResponseTimeout>()
+            .expect("must time out");
+        assert_eq!(timeout.duration(), Duration::from_secs(2));
+    }
+
+    #[test]
+    fn try_new_rejects_invalid_durations() {
+        let zero = Timeout::try_new((), Duration::from_secs(0)).expect_err("must reject zero");
+        assert_eq!(zero.duration(), Duration::from_secs(0));
+        assert!(Timeout::try_new((), Duration::from_nanos(1)).is_ok());
+
+        assert!(Timeout::try_new((), MAX_TIMEOUT).is_ok());
+        let max = MAX_TIMEOUT + Duration::from_nanos(1);
+        let err = Timeout::try_new((), max).expect_err("must reject durations over the max");
+        assert_eq!(err.duration(), max);
+    }
+
+    #[tokio::test]
+    async fn passthru_never_times_out() {
+        time::pause();
+        let (tx, rx) = futures::channel::oneshot::channel::<()>();
+        let mut rx = Some(rx);
+        let inner = tower::service_fn(move |()| rx.take().unwrap().map_err(Error::from));
+        let mut svc = Timeout::passthru(inner);
+        let mut fut = tokio_test::task::spawn(svc.call(()));
+
+        time::advance(Duration::from_secs(60 * 60 * 24 * 365)).await;
+        tokio_test::assert_pending!(fut.poll());
+
+        tx.send(()).unwrap();
+        tokio_test::assert_ready_ok!(fut.poll());
+    }
+
+    #[tokio::test]
+    async fn into_inner_recovers_service() {
+        let (inner, mut handle) = tower_test::mock::pair::<(), ()>();
+        let svc = Timeout::new(inner, Duration::from_secs(1)).into_inner();
+
+        // The recovered service can be rewrapped and used.
+        let mut svc = tower_test::mock::Spawn::new(Timeout::passthru(svc));
+        handle.allow(1);
+        tokio_test::assert_ready_ok!(svc.poll_ready());
+        let fut = svc.call(());
+
+        let ((), rsp) = handle.next_request().await.expect("must get a request");
+        rsp.send_response(());
+        assert!(fut.await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn on_complete_reports_budget() {
+        time::pause();
+        let (tx, rx) = futures::channel::oneshot::channel::<()>();
+        let mut rx = Some(rx);
+        let inner = tower::service_fn(move |()| rx.take().unwrap().map_err(Error::from));
+        let budget = Arc::new(std::sync::Mutex::new(None));
+        let mut svc = Timeout::new(inner, Duration::from_millis(500)).on_complete({
+            let budget = budget.clone();
+            move |consumed, remaining| {
+                *budget.lock().unwrap() = Some((consumed, remaining));
+            }
+        });
+        let mut fut = tokio_test::task::spawn(svc.call(()));
+        tokio_test::assert_pending!(fut.poll());
+
+        time::advance(Duration::from_millis(300)).await;
+        tx.send(()).unwrap();
+        tokio_test::assert_ready_ok!(fut.poll());
+        assert_eq!(
+            *budget.lock().unwrap(),
+            Some((Duration::from_millis(300), Duration::from_millis(200)))
+        );
+    }
+
+    #[tokio::test]
+    async fn frequent_timeouts_escalate_severity() {
+        time::pause();
+        let inner = tower::service_fn(|()| future::pending::<Result<(), Error>>());
+        let mut svc = Timeout::new(inner, Duration::from_secs(1))
+            .with_severity_window(Duration::from_secs(60), 2);
+
+        let mut time_out = || {
+            let mut fut = tokio_test::task::spawn(svc.call(()));
+            async move {
+                tokio_test::assert_pending!(fut.poll());
+                time::advance(Duration::from_millis(1_001)).await;
+                let err = tokio_test::assert_ready_err!(fut.poll());
+                err.downcast_ref::<error::ResponseTimeout>()
+                    .expect("must time out")
+                    .severity()
+            }
+        };
+
+        assert_eq!(time_out().await, error::Severity::Info);
+        assert_eq!(time_out().await, error::Severity::Info);
+        assert_eq!(time_out().await, error::Severity::Warn);
+
+        // Once the earlier timeouts leave the window, severity drops again.
+        time::advance(Duration::from_secs(60)).await;
+        assert_eq!(time_out().await, error::Severity::Info);
+    }
+
+    #[tokio::test]
+    async fn huge_severity_thresholds_do_not_overflow() {
+        time::pause();
+        let inner = tower::service_fn(|()| future::pending::<Result<(), Error>>());
+        let mut svc = Timeout::new(inner, Duration::from_secs(1))
+            .with_severity_window(Duration::from_secs(60), usize::MAX);
+
+        let mut fut = tokio_test::task::spawn(svc.call(()));
+        tokio_test::assert_pending!(fut.poll());
+        time::advance(Duration::from_millis(1_001)).await;
+        let err = tokio_test::assert_ready_err!(fut.poll());
+        let severity = err
+            .downcast_ref::<error::ResponseTimeout>()
+            .expect("must time out")
+            .severity();
+        assert_eq!(severity, error::Severity::Info);
+    }
+
+    #[test]
+    fn get_ref_and_get_mut_access_inner() {
+        let mut svc = Timeout::new(vec![1], Duration::from_secs(1));
+        assert_eq!(svc.get_ref(), &vec![1]);
+
+        svc.get_mut().push(2);
+        assert_eq!(svc.get_ref(), &vec![1, 2]);
+        assert_eq!(svc.into_inner(), vec![1, 2]);
+    }
+
+    #[tokio::test]
+    async fn passthru_has_no_remaining() {
+        let inner = tower::service_fn(|()| future::pending::<Result<(), Error>>());
+        let mut svc = Timeout::passthru(inner);
+        assert_eq!(svc.call(()).remaining(), None);
+    }
+}
@@ -0,0 +1,435 @@
+//! A middleware that retries a connection attempt once if it times out.
+
+use crate::error::ResponseTimeout;
+use linkerd2_error::Error;
+use pin_project::pin_project;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::{
+    atomic::{AtomicUsize, Ordering},
+    Arc,
+};
+use std::task::{Context, Poll};
+use tower::util::{Oneshot, ServiceExt};
+use tracing::debug;
+
+#[derive(Copy, Clone, Debug, Default)]
+pub struct RetryOnTimeoutLayer {
+    max_attempts: Option<usize>,
+}
+
+/// Retries a connection once if it fails with a `ResponseTimeout`.
+///
+/// This is intended to wrap a connect stack that includes a `Timeout`, so that
+/// the retried attempt gets a fresh deadline. Other errors, and a second
+/// timeout, are returned unchanged.
+#[derive(Clone, Debug)]
+pub struct RetryOnTimeout<S> {
+    inner: S,
+    max_attempts: Option<usize>,
+}
+
+/// Limits the number of attempts issued on behalf of one logical operation.
+///
+/// Clones share the same budget, so a single budget may bound the attempts
+/// made by several layers. A budget never refills, so each operation should
+/// get its own.
+#[derive(Clone, Debug)]
+pub struct AttemptBudget(Arc<AtomicUsize>);
+
+/// An error indicating that a connection was not attempted because the
+/// `AttemptBudget` was exhausted.
+#[derive(Debug)]
+pub struct AttemptBudgetExhausted(());
+
+#[pin_project]
+pub struct RetryFuture<S: tower::Service<T>, T> {
+    retry: Option<(S, T)>,
+    budget: Option<AttemptBudget>,
+    #[pin]
+    state: State<S::Future, Oneshot<S, T>>,
+}
+
+#[pin_project(project = StateProj)]
+pub enum State<F, R> {
+    Exhausted,
+    First(#[pin] F),
+    Retry(#[pin] R),
+}
+
+// === impl RetryOnTimeoutLayer ===
+
+impl RetryOnTimeoutLayer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Limits each connection to `max_attempts` attempts, including the
+    /// first.
+    ///
+    /// # Panics
+    ///
+    /// If `max_attempts` is zero, since no connection could ever be attempted.
+    pub fn with_max_attempts(self, max_attempts: usize) -> Self {
+        assert!(max_attempts > 0, "max_attempts must be at least 1");
+        Self {
+            max_attempts: Some(max_attempts),
+        }
+    }
+}
+
+impl<S> tower::layer::Layer<S> for RetryOnTimeoutLayer {
+    type Service = RetryOnTimeout<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        Self::Service {
+            inner,
+            max_attempts: self.max_attempts,
+        }
+    }
+}
+
+// === impl RetryOnTimeout ===
+
+impl<S> RetryOnTimeout<S> {
+    pub fn new(inner: S) -> Self {
+        Self {
+            inner,
+            max_attempts: None,
+        }
+    }
+
+    /// Limits each connection to `max_attempts` attempts, including the
+    /// first.
+    ///
+    /// Each call gets a fresh `AttemptBudget`.
+    ///
+    /// # Panics
+    ///
+    /// If `max_attempts` is zero, since no connection could ever be attempted.
+    pub fn with_max_attempts(self, max_attempts: usize) -> Self {
+        assert!(max_attempts > 0, "max_attempts must be at least 1");
+        Self {
+            max_attempts: Some(max_attempts),
+            ..self
+        }
+    }
+
+    /// Connects to `target`, drawing each attempt from `budget`.
+    ///
+    /// This lets a budget created for a logical operation be shared with
+    /// other layers that issue attempts for it. When the budget is exhausted,
+    /// a first attempt fails with `AttemptBudgetExhausted` and a timed-out
+    /// attempt is not retried.
+    pub fn call_with_budget<T>(&mut self, target: T, budget: AttemptBudget) -> RetryFuture<S, T>
+    where
+        T: Clone,
+        S: tower::Service<T> + Clone,
+        S::Error: Into<Error>,
+    {
+        self.call_inner(target, Some(budget))
+    }
+
+    fn call_inner<T>(&mut self, target: T, budget: Option<AttemptBudget>) -> RetryFuture<S, T>
+    where
+        T: Clone,
+        S: tower::Service<T> + Clone,
+    {
+        let state = if budget.as_ref().map_or(true, AttemptBudget::try_acquire) {
+            State::First(self.inner.call(target.clone()))
+        } else {
+            State::Exhausted
+        };
+        RetryFuture {
+            retry: Some((self.inner.clone(), target)),
+            budget,
+            state,
+        }
+    }
+}
+
+impl<S, T> tower::Service<T> for RetryOnTimeout<S>
+where
+    T: Clone,
+    S: tower::Service<T> + Clone,
+    S::Error: Into<Error>,
+{
+    type Response = S::Response;
+    type Error = Error;
+    type Future = RetryFuture<S, T>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx).map_err(Into::into)
+    }
+
+    fn call(&mut self, target: T) -> Self::Future {
+        let budget = self.max_attempts.map(AttemptBudget::new);
+        self.call_inner(target, budget)
+    }
+}
+
+// === impl RetryFuture ===
+
+impl<S, T> Future for RetryFuture<S, T>
+where
+    S: tower::Service<T>,
+    S::Error: Into<Error>,
+{
+    type Output = Result<S::Response, Error>;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let mut this = self.project();
+        loop {
+            let res = match this.state.as_mut().project() {
+                StateProj::Exhausted => {
+                    return Poll::Ready(Err(AttemptBudgetExhausted(()).into()));
+                }
+                StateProj::First(f) => futures::ready!(f.poll(cx)),
+                StateProj::Retry(f) => futures::ready!(f.poll(cx)),
+            };
+            let error: Error = match res {
+                Ok(rsp) => return Poll::Ready(Ok(rsp)),
+                Err(e) => e.into(),
+            };
+
+            match this.retry.take() {
+                Some((svc, target)) if error.is::<ResponseTimeout>() => {
+                    if !this
+                        .budget
+                        .as_ref()
+                        .map_or(true, AttemptBudget::try_acquire)
+                    {
+                        debug!(%error, "Attempt budget exhausted; not retrying");
+                        return Poll::Ready(Err(error));
+                    }
+                    debug!(%error, "Retrying");
+                    this.state.set(State::Retry(svc.oneshot(target)));
+                }
+                _ => return Poll::Ready(Err(error)),
+            }
+        }
+    }
+}
+
+// === impl AttemptBudget ===
+
+impl AttemptBudget {
+    /// Creates a budget that allows `max_attempts` attempts.
+    ///
+    /// # Panics
+    ///
+    /// If `max_attempts` is zero, since such a budget could never be used.
+    pub fn new(max_attempts: usize) -> Self {
+        assert!(max_attempts > 0, "max_attempts must be at least 1");
+        AttemptBudget(Arc::new(AtomicUsize::new(max_attempts)))
+    }
+
+    /// Returns the number of attempts that may still be issued.
+    pub fn remaining(&self) -> usize {
+        self.0.load(Ordering::Acquire)
+    }
+
+    /// Takes an attempt from the budget, returning false if it's exhausted.
+    pub fn try_acquire(&self) -> bool {
+        let mut remaining = self.0.load(Ordering::Acquire);
+        loop {
+            if remaining == 0 {
+                return false;
+            }
+            match self.0.compare_exchange_weak(
+                remaining,
+                remaining - 1,
+                Ordering::AcqRel,
+                Ordering::Acquire,
+            ) {
+                Ok(_) => return true,
+                Err(actual) => remaining = actual,
+            }
+        }
+    }
+}
+
+// === impl AttemptBudgetExhausted ===
+
+impl std::fmt::Display for AttemptBudgetExhausted {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "attempt budget exhausted")
+    }
+}
+
+impl std::error::Error for AttemptBudgetExhausted {}
+
+#[cfg(test)]
+mod test {
+    use super::{AttemptBudget, AttemptBudgetExhausted, RetryOnTimeout};
+    use crate::{error::ResponseTimeout, Timeout};
+    use futures::future;
+    use linkerd2_error::Error;
+    use std::future::Future;
+    use std::io;
+    use std::pin::Pin;
+    use std::sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    };
+    use std::task::{Context, Poll};
+    use std::time::Duration;
+    use tokio::time;
+    use tokio_test::{assert_pending, assert_ready, assert_ready_ok, task};
+    use tower::{Service, ServiceExt};
+
+    type Connected = io::Cursor<Vec<u8>>;
+
+    #[derive(Copy, Clone, Debug)]
+    enum Attempt {
+        Stall,
+        Connect,
+        Fail,
+    }
+
+    /// Connects according to a script of attempts.
+    #[derive(Clone, Debug)]
+    struct MockConnect {
+        script: Arc<Vec<Attempt>>,
+        attempts: Arc<AtomicUsize>,
+    }
+
+    impl MockConnect {
+        fn new(script: Vec<Attempt>) -> Self {
+            Self {
+                script: Arc::new(script),
+                attempts: Arc::new(AtomicUsize::new(0)),
+            }
+        }
+
+        fn attempts(&self) -> usize {
+            self.attempts.load(Ordering::SeqCst)
+        }
+    }
+
+    impl Service<()> for MockConnect {
+        type Response = Connected;
+        type Error = Error;
+        type Future = Pin<Box<dyn Future<Output = Result<Connected, Error>> + Send>>;
+
+        fn poll_ready(&mut self, _: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+            Poll::Ready(Ok(()))
+        }
+
+        fn call(&mut self, (): ()) -> Self::Future {
+            let attempt = self.attempts.fetch_add(1, Ordering::SeqCst);
+            match self.script[attempt] {
+                Attempt::Stall => Box::pin(future::pending()),
+                Attempt::Connect => Box::pin(future::ok(io::Cursor::new(Vec::new()))),
+                Attempt::Fail => Box::pin(future::err("connection refused".into())),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn retries_after_timeout() {
+        time::pause();
+        let mock = MockConnect::new(vec![Attempt::Stall, Attempt::Connect]);
+        let connect = RetryOnTimeout::new(Timeout::new(mock.clone(), Duration::from_secs(1)));
+
+        let mut fut = task::spawn(connect.oneshot(()));
+        assert_pending!(fut.poll());
+        assert_eq!(mock.attempts(), 1);
+
+        time::advance(Duration::from_millis(1_001)).await;
+        assert_ready_ok!(fut.poll());
+        assert_eq!(mock.attempts(), 2);
+    }
+
+    #[tokio::test]
+    async fn only_retries_once() {
+        time::pause();
+        let mock = MockConnect::new(vec![Attempt::Stall, Attempt::Stall]);
+        let connect = RetryOnTimeout::new(Timeout::new(mock.clone(), Duration::from_secs(1)));
+
+        let mut fut = task::spawn(connect.oneshot(()));
+        assert_pending!(fut.poll());
+
+        time::advance(Duration::from_millis(1_001)).await;
+        assert_pending!(fut.poll());
+        assert_eq!(mock.attempts(), 2);
+
+        time::advance(Duration::from_millis(1_001)).await;
+        let err = assert_ready!(fut.poll()).expect_err("must time out");
+        assert!(err.is::<ResponseTimeout>());
+        assert_eq!(mock.attempts(), 2);
+    }
+
+    #[tokio::test]
+    async fn max_attempts_applies_per_connection() {
+        time::pause();
+        let mut script = vec![Attempt::Stall];
+        script.extend(vec![Attempt::Connect; 20]);
+        let mock = MockConnect::new(script);
+        let connect = RetryOnTimeout::new(Timeout::new(mock.clone(), Duration::from_secs(1)))
+            .with_max_attempts(1);
+
+        // The timed-out attempt isn't retried.
+        let mut fut = task::spawn(connect.clone().oneshot(()));
+        assert_pending!(fut.poll());
+        time::advance(Duration::from_millis(1_001)).await;
+        let err = assert_ready!(fut.poll()).expect_err("must time out");
+        assert!(err.is::<ResponseTimeout>());
+
+        // Each later connection gets its own budget.
+        for _ in 0..20 {
+            connect.clone().oneshot(()).await.expect("must connect");
+        }
+        assert_eq!(mock.attempts(), 21);
+    }
+
+    #[tokio::test]
+    async fn shared_budget_caps_attempts() {
+        time::pause();
+        let mock = MockConnect::new(vec![Attempt::Stall; 4]);
+        let mut a = RetryOnTimeout::new(Timeout::new(mock.clone(), Duration::from_secs(1)));
+        let mut b = a.clone();
+        // A single logical operation, issuing attempts through two layers.
+        let budget = AttemptBudget::new(3);
+
+        // The first connection uses two attempts.
+        ServiceExt::<()>::ready_and(&mut a).await.unwrap();
+        let mut fut = task::spawn(a.call_with_budget((), budget.clone()));
+        assert_pending!(fut.poll());
+        time::advance(Duration::from_millis(1_001)).await;
+        assert_pending!(fut.poll());
+        time::advance(Duration::from_millis(1_001)).await;
+        assert!(assert_ready!(fut.poll()).is_err());
+        assert_eq!(budget.remaining(), 1);
+
+        // The second gets one attempt and is not retried.
+        ServiceExt::<()>::ready_and(&mut b).await.unwrap();
+        let mut fut = task::spawn(b.call_with_budget((), budget.clone()));
+        assert_pending!(fut.poll());
+        time::advance(Duration::from_millis(1_001)).await;
+        let err = assert_ready!(fut.poll()).expect_err("must time out");
+        assert!(err.is::<ResponseTimeout>());
+
+        // Further attempts for this operation aren't issued at all.
+        ServiceExt::<()>::ready_and(&mut a).await.unwrap();
+        let err = a.call_with_budget((), budget).await.expect_err("must fail");
+        assert!(err.is::<AttemptBudgetExhausted>());
+        assert_eq!(mock.attempts(), 3);
+    }
+
+    #[tokio::test]
+    async fn does_not_retry_inner_errors() {
+        let mock = MockConnect::new(vec![Attempt::Fail, Attempt::Connect]);
+        let connect = RetryOnTimeout::new(Timeout::new(mock.clone(), Duration::from_secs(1)));
+
+        let err = connect.oneshot(()).await.expect_err("must fail");
+        assert!(!err.is::<ResponseTimeout>());
+        assert_eq!(mock.attempts(), 1);
+    }
+
+    #[test]
+    #[should_panic]
+    fn rejects_empty_budgets() {
+        AttemptBudget::new(0);
+    }
+}
@@ -0,0 +1,54 @@
+//! Tracks how often a `Timeout` fires to classify the severity of timeouts.
+
+use crate::error::Severity;
+use std::collections::VecDeque;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// A sliding window of recent timeouts, shared by clones of a `Timeout`.
+#[derive(Clone, Debug)]
+pub struct SeverityWindow {
+    window: Duration,
+    threshold: usize,
+    fired: Arc<Mutex<VecDeque<Instant>>>,
+}
+
+// === impl SeverityWindow ===
+
+impl SeverityWindow {
+    pub(crate) fn new(window: Duration, threshold: usize) -> Self {
+        Self {
+            window,
+            threshold,
+            // The queue isn't pre-sized, since the threshold may be
+            // arbitrarily large.
+            fired: Arc::new(Mutex::new(VecDeque::new())),
+        }
+    }
+
+    /// Records that a timeout fired, returning its severity.
+    pub(crate) fn record(&self) -> Severity {
+        let now = Instant::now();
+        let mut fired = self.fired.lock().expect("severity window lock poisoned");
+        while let Some(t) = fired.front() {
+            if now.saturating_duration_since(*t) < self.window {
+                break;
+            }
+            fired.pop_front();
+        }
+
+        // Only the most recent `threshold + 1` timeouts are needed to know
+        // whether the threshold has been exceeded.
+        if fired.len() > self.threshold {
+            fired.pop_front();
+        }
+        fired.push_back(now);
+
+        if fired.len() > self.threshold {
+            Severity::Warn
+        } else {
+            Severity::Info
+        }
+    }
+}
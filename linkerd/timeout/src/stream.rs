@@ -0,0 +1,161 @@
+//! A stream wrapper that limits the amount of time between items.
+
+use crate::{error::ItemTimeout, MAX_TIMEOUT};
+use futures::{Stream, TryStream};
+use linkerd2_error::Error;
+use pin_project::pin_project;
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+use tokio::time::{self, Delay, Instant};
+
+/// Fails a stream if an item is not produced within `duration`.
+///
+/// The deadline is armed when a poll finds no item ready and is cleared each
+/// time the stream yields, so `duration` bounds the time spent waiting for
+/// each item rather than for the stream as a whole. Time during which the
+/// stream is not being polled, including before its first poll, does not
+/// consume the budget.
+#[pin_project]
+#[derive(Debug)]
+pub struct TimeoutStream<S> {
+    #[pin]
+    inner: S,
+    duration: Duration,
+    delay: Option<Delay>,
+    armed: bool,
+}
+
+// === impl TimeoutStream ===
+
+impl<S> TimeoutStream<S> {
+    pub fn new(inner: S, duration: Duration) -> Self {
+        Self {
+            inner,
+            duration,
+            delay: None,
+            armed: false,
+        }
+    }
+}
+
+impl<S> Stream for TimeoutStream<S>
+where
+    S: TryStream,
+    S::Error: Into<Error>,
+{
+    type Item = Result<S::Ok, Error>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.project();
+
+        if let Poll::Ready(item) = this.inner.try_poll_next(cx) {
+            *this.armed = false;
+            return Poll::Ready(item.map(|res| res.map_err(Into::into)));
+        }
+
+        // The timer is created on the first poll, so that a stream may be
+        // constructed outside of the runtime, and the same `Delay` is reset
+        // for each item to avoid re-registering a timer. Capping the deadline
+        // keeps `Instant` arithmetic from overflowing.
+        let duration = (*this.duration).min(MAX_TIMEOUT);
+        if this.delay.is_none() {
+            *this.delay = Some(time::delay_for(duration));
+            *this.armed = true;
+        }
+        let delay = this.delay.as_mut().expect("delay must be set");
+        if !*this.armed {
+            delay.reset(Instant::now() + duration);
+            *this.armed = true;
+        }
+        futures::ready!(Pin::new(delay).poll(cx));
+
+        // The stream may continue to be polled after a timeout, with a new
+        // deadline.
+        *this.armed = false;
+        Poll::Ready(Some(Err(ItemTimeout(*this.duration).into())))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TimeoutStream;
+    use crate::error::ItemTimeout;
+    use futures::channel::mpsc;
+    use linkerd2_error::Error;
+    use std::time::Duration;
+    use tokio::time;
+    use tokio_test::{assert_pending, assert_ready, task};
+
+    #[tokio::test]
+    async fn times_out_stalled_items() {
+        time::pause();
+        let (tx, rx) = mpsc::unbounded::<Result<(), Error>>();
+        let mut stream = task::spawn(TimeoutStream::new(rx, Duration::from_secs(1)));
+
+        tx.unbounded_send(Ok(())).unwrap();
+        assert!(matches!(assert_ready!(stream.poll_next()), Some(Ok(()))));
+
+        // Time spent before the stream is polled again doesn't count.
+        time::advance(Duration::from_millis(999)).await;
+        tx.unbounded_send(Ok(())).unwrap();
+        assert!(matches!(assert_ready!(stream.poll_next()), Some(Ok(()))));
+        time::advance(Duration::from_secs(5)).await;
+
+        // The deadline is armed once the stream is polled and has no item.
+        assert_pending!(stream.poll_next());
+        time::advance(Duration::from_millis(999)).await;
+        assert_pending!(stream.poll_next());
+
+        time::advance(Duration::from_millis(1)).await;
+        let err = assert_ready!(stream.poll_next())
+            .expect("stream must not end")
+            .expect_err("item must time out");
+        assert!(err.is::<ItemTimeout>());
+    }
+
+    #[tokio::test]
+    async fn deadline_arms_on_first_poll() {
+        time::pause();
+        let (tx, rx) = mpsc::unbounded::<Result<(), Error>>();
+        let mut stream = task::spawn(TimeoutStream::new(rx, Duration::from_secs(1)));
+
+        // Idle time before the stream is first polled doesn't count.
+        time::advance(Duration::from_secs(2)).await;
+        assert_pending!(stream.poll_next());
+
+        time::advance(Duration::from_millis(999)).await;
+        tx.unbounded_send(Ok(())).unwrap();
+        assert!(matches!(assert_ready!(stream.poll_next()), Some(Ok(()))));
+    }
+
+    #[test]
+    fn constructs_outside_runtime() {
+        let (_tx, rx) = mpsc::unbounded::<Result<(), Error>>();
+        let _stream = TimeoutStream::new(rx, Duration::from_secs(std::u64::MAX));
+    }
+
+    #[tokio::test]
+    async fn huge_durations_do_not_overflow() {
+        time::pause();
+        let (_tx, rx) = mpsc::unbounded::<Result<(), Error>>();
+        let mut stream = task::spawn(TimeoutStream::new(rx, Duration::from_secs(std::u64::MAX)));
+
+        assert_pending!(stream.poll_next());
+        time::advance(Duration::from_secs(60 * 60 * 24)).await;
+        assert_pending!(stream.poll_next());
+    }
+
+    #[tokio::test]
+    async fn ends_normally() {
+        time::pause();
+        let (tx, rx) = mpsc::unbounded::<Result<(), Error>>();
+        let mut stream = task::spawn(TimeoutStream::new(rx, Duration::from_secs(1)));
+
+        tx.unbounded_send(Ok(())).unwrap();
+        drop(tx);
+        assert!(matches!(assert_ready!(stream.poll_next()), Some(Ok(()))));
+        assert!(assert_ready!(stream.poll_next()).is_none());
+    }
+}